@@ -1,44 +1,1062 @@
+#[cfg(feature = "bnf")]
+use std::fmt;
+use std::collections::HashMap;
+
 use anyhow::{Context, Result};
-use bnf::{Grammar, ParseTree};
+use bnf::Grammar;
+#[cfg(feature = "bnf")]
+use bnf::{ParseTree, Term};
 
-use super::formula::Formula;
+use super::formula::{Formula, Term as FoTerm};
 
 static BNF_GRAMMAR: &str = include_str!("grammar.bnf");
 
+/// A structured parse failure locating *where* and *why* the grammar rejected
+/// the input, instead of the opaque "could not parse" context string.
+///
+/// bnf's Earley parser only yields complete parses and never reports a position,
+/// so the failure location is recovered by driving the hand-written LL(1)
+/// frontend — which accepts the same language incrementally — to the first
+/// character or token it cannot consume. `expected` is best-effort — bnf 0.6 does
+/// not expose the Earley item set, so we probe the grammar's terminal alphabet
+/// for the symbols that would extend the consumed prefix.
+#[cfg(feature = "bnf")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ParseError {
+    /// Byte offset of the first unexpected character.
+    pub(crate) offset: usize,
+    /// 1-based line of `offset`.
+    pub(crate) line: usize,
+    /// 1-based column of `offset`.
+    pub(crate) column: usize,
+    /// The input before `offset`: what the hand-written frontend consumed.
+    pub(crate) consumed: String,
+    /// Grammar terminals that, appended to `consumed`, form an accepted formula.
+    pub(crate) expected: Vec<String>,
+}
+
+#[cfg(feature = "bnf")]
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "parse error at line {}, column {} (byte {})",
+            self.line, self.column, self.offset
+        )?;
+        if !self.expected.is_empty() {
+            write!(f, "; expected one of {}", self.expected.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "bnf")]
+impl std::error::Error for ParseError {}
+
 pub(crate) struct Parser {
     grammar: Grammar,
+    macros: MacroRegistry,
 }
 
 impl Parser {
     pub(crate) fn new() -> Result<Self> {
         let grammar: Grammar = BNF_GRAMMAR.parse().context("Couldn't parse grammar")?;
-        Ok(Self { grammar })
+        Ok(Self {
+            grammar,
+            macros: MacroRegistry::default(),
+        })
+    }
+
+    /// Registers a desugaring abbreviation written as `Head p1 p2 := <template>`,
+    /// e.g. `Xor a b := And (Or (a) (b)) (Not (And (a) (b)))`. Once registered,
+    /// the recursive-descent backend expands calls to `Head` inline into core
+    /// [`Formula`] nodes, substituting each parenthesised argument for its
+    /// placeholder — letting callers extend the surface language without grammar
+    /// edits while the `Formula` enum stays minimal.
+    pub(crate) fn define_macro(&mut self, definition: &str) -> Result<()> {
+        self.macros.register(definition)
     }
 
     /// Builds a parser, parses `formula`, and runs `f` on the first parse tree.
     /// Use this instead of returning a `ParseTree` because in bnf 0.6 parse trees borrow from the parser.
+    #[cfg(feature = "bnf")]
     pub(crate) fn with_parse_tree<F, R>(&self, formula: &str, f: F) -> Result<R>
     where
         F: for<'p> FnOnce(&ParseTree<'p>) -> R,
     {
         let parser = self.grammar.build_parser().context("Couldn't build parser")?;
-        let parse_tree = parser
-            .parse_input(formula)
-            .next()
-            .context(format!("Grammar could not parse input: {}", formula))?;
-        Ok(f(&parse_tree))
+        match parser.parse_input(formula).next() {
+            Some(parse_tree) => Ok(f(&parse_tree)),
+            None => Err(self.locate_error(formula).into()),
+        }
     }
 
+    /// Parses `formula` to a [`Formula`] with the crate's default backend.
+    ///
+    /// With the `bnf` feature this drives the Earley engine, unless a macro is
+    /// registered; otherwise it falls through to the hand-written
+    /// [`Parser::parse_recursive`] descent.
     pub(crate) fn parse<'a>(&'a self, formula: &'a str) -> Result<Formula> {
-        self.with_parse_tree(formula, Formula::parse_input)?
+        #[cfg(feature = "bnf")]
+        {
+            // Desugaring abbreviations are expanded by the recursive-descent
+            // backend; the bnf grammar has no knowledge of their heads. Route
+            // through it whenever any macro is registered so `parse` honours
+            // them identically with or without the `bnf` feature, rather than
+            // rejecting `Xor …` as an unknown symbol.
+            if !self.macros.is_empty() {
+                return self.parse_recursive(formula);
+            }
+            self.with_parse_tree(formula, Formula::parse_input)?
+        }
+        #[cfg(not(feature = "bnf"))]
+        {
+            self.parse_recursive(formula)
+        }
+    }
+
+    /// Parses `formula` by direct recursive descent into [`Formula`], without
+    /// compiling a grammar or materialising a `ParseTree`.
+    ///
+    /// Our concrete syntax is LL(1) — every construct is disambiguated by a
+    /// leading keyword — so a single tokenisation pass followed by recursive
+    /// descent is both allocation-light and free of the per-formula grammar
+    /// rebuild the bnf path pays in `with_parse_tree`.
+    pub(crate) fn parse_recursive(&self, formula: &str) -> Result<Formula> {
+        let tokens = tokenize(formula)?;
+        let mut cursor = TokenCursor::new(&tokens, &self.macros);
+        let parsed = cursor.formula()?;
+        if let Some(token) = cursor.peek() {
+            anyhow::bail!("unexpected trailing input: {:?}", token);
+        }
+        Ok(parsed)
+    }
+
+    /// Parses `formula` written in conventional infix/Unicode notation —
+    /// e.g. `D(x) -> ∀y. D(y)` — into the same [`Formula`] type as [`parse`].
+    ///
+    /// This is an alternate frontend for people writing textbook syntax rather
+    /// than the verbose prefix/ADT form; it shares nothing with the bnf grammar.
+    ///
+    /// [`parse`]: Parser::parse
+    pub(crate) fn parse_infix(&self, formula: &str) -> Result<Formula> {
+        let tokens = tokenize_infix(formula)?;
+        let mut cursor = InfixCursor::new(&tokens);
+        let parsed = cursor.iff()?;
+        if let Some(token) = cursor.peek() {
+            anyhow::bail!("unexpected trailing input: {:?}", token);
+        }
+        Ok(parsed)
+    }
+
+    /// Locates the failure in an input the grammar rejected.
+    ///
+    /// The bnf Earley engine only ever accepts *whole* formulas, so no proper
+    /// prefix of a valid formula is itself valid — probing prefixes of the
+    /// grammar cannot say *where* the input broke. Instead we drive the
+    /// hand-written LL(1) tokenizer and recursive descent — which accept the same
+    /// language incrementally — and report the byte offset of the first character
+    /// or token they cannot consume.
+    #[cfg(feature = "bnf")]
+    fn locate_error(&self, input: &str) -> ParseError {
+        let offset = self.descent_failure_offset(input);
+        let consumed = input[..offset].to_string();
+
+        let (mut line, mut column) = (1usize, 1usize);
+        for ch in input[..offset].chars() {
+            if ch == '\n' {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+        }
+
+        // `expected` stays best-effort: probe the grammar's terminals through a
+        // single reused parser, reporting those that would extend the consumed
+        // prefix into a complete formula.
+        let expected = match self.grammar.build_parser() {
+            Ok(parser) => {
+                let accepts = |candidate: &str| parser.parse_input(candidate).next().is_some();
+                Self::expected_after(&self.grammar, &accepts, &consumed)
+            }
+            Err(_) => Vec::new(),
+        };
+
+        ParseError {
+            offset,
+            line,
+            column,
+            consumed,
+            expected,
+        }
+    }
+
+    /// Byte offset of the first character or token the hand-written frontend
+    /// cannot consume: the lexical error position if tokenizing stops early, else
+    /// the offset the recursive descent reached before giving up (end of input
+    /// when it consumed everything yet the grammar still rejected the whole).
+    #[cfg(feature = "bnf")]
+    fn descent_failure_offset(&self, input: &str) -> usize {
+        let (tokens, spans, lex_error) = tokenize_tolerant(input);
+        let mut cursor = TokenCursor::new(&tokens, &self.macros);
+        let structural = match cursor.formula() {
+            // A complete parse: the offending byte is the first leftover token,
+            // or the end of input when everything was consumed.
+            Ok(_) => spans.get(cursor.pos).copied(),
+            // A failure: `next` steps past each token it hands out, so a rejected
+            // token sits just before the cursor; running out of tokens leaves the
+            // cursor on the end-of-input entry.
+            Err(error) => {
+                let ran_out = error.root_cause().to_string() == "unexpected end of input";
+                let index = if ran_out { cursor.pos } else { cursor.pos.saturating_sub(1) };
+                Some(spans[index.min(spans.len() - 1)])
+            }
+        };
+        match (lex_error, structural) {
+            (Some(lex), Some(structural)) => lex.min(structural),
+            (Some(lex), None) => lex,
+            (None, Some(structural)) => structural,
+            (None, None) => input.len(),
+        }
+    }
+
+    /// Best-effort set of terminals that would extend an accepted prefix: each
+    /// grammar terminal `t` for which `consumed + t` is itself accepted, probed
+    /// through the caller's already-built `accepts` rather than a fresh parser.
+    ///
+    /// bnf 0.6 exposes neither the Earley item set nor the nonterminals pending
+    /// at a position, so "what was expected here" is approximated from the
+    /// grammar's terminal alphabet.
+    #[cfg(feature = "bnf")]
+    fn expected_after(
+        grammar: &Grammar,
+        accepts: &impl Fn(&str) -> bool,
+        consumed: &str,
+    ) -> Vec<String> {
+        let mut expected = Vec::new();
+        for production in grammar.productions_iter() {
+            for expression in production.rhs_iter() {
+                for term in expression.terms_iter() {
+                    if let Term::Terminal(terminal) = term {
+                        if !expected.iter().any(|e| e == terminal)
+                            && accepts(&format!("{}{}", consumed, terminal))
+                        {
+                            expected.push(terminal.clone());
+                        }
+                    }
+                }
+            }
+        }
+        expected
+    }
+
+    /// Parses an input that may contain several formulas (or several errors),
+    /// continuing past each failure by resynchronising at the next top-level
+    /// line so a batch file surfaces *all* of its problems at once.
+    ///
+    /// The idea mirrors Dhall's recoverable `FailedParse` node and rustfmt's
+    /// `catch_unwind` wrapper: one bad formula should not hide the rest.
+    #[cfg(feature = "bnf")]
+    pub(crate) fn parse_recovering(&self, input: &str) -> (Vec<Formula>, Vec<ParseError>) {
+        let mut formulas = Vec::new();
+        let mut errors = Vec::new();
+
+        let mut rest = input.trim();
+        while !rest.is_empty() {
+            let (chunk, tail) = split_next_formula(rest);
+            rest = tail;
+            if chunk.is_empty() {
+                continue;
+            }
+            match self.parse(chunk) {
+                Ok(formula) => formulas.push(formula),
+                Err(error) => match error.downcast::<ParseError>() {
+                    Ok(parse_error) => errors.push(parse_error),
+                    // A non-`ParseError` (e.g. a failed parser build) carries no
+                    // position: record it at the chunk start with its message in
+                    // `expected`, and go on with the next chunk.
+                    Err(other) => {
+                        errors.push(ParseError {
+                            offset: 0,
+                            line: 1,
+                            column: 1,
+                            consumed: String::new(),
+                            expected: vec![other.to_string()],
+                        });
+                    }
+                },
+            }
+        }
+
+        (formulas, errors)
+    }
+}
+
+/// Splits off the next formula at the first *top-level* newline — one reached
+/// while paren/bracket depth is zero — returning `(chunk, rest)`. Used by
+/// [`Parser::parse_recovering`] to resynchronise after an error.
+///
+/// Resync is line-oriented, not paren-oriented: the prefix syntax puts each head
+/// keyword *outside* its argument groups (`And (a) (b)`), so paren depth returns
+/// to zero in the middle of a valid formula. Cutting at the first balanced paren
+/// would slice a multi-argument formula apart and manufacture spurious errors —
+/// the opposite of surfacing every real problem at once.
+#[cfg(feature = "bnf")]
+fn split_next_formula(input: &str) -> (&str, &str) {
+    let mut depth = 0i32;
+    for (i, ch) in input.char_indices() {
+        match ch {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            '\n' if depth <= 0 => {
+                return (input[..i].trim(), input[i + 1..].trim_start());
+            }
+            _ => {}
+        }
+    }
+    (input.trim(), "")
+}
+
+/// A lexical token of the prefix/ADT concrete syntax.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    /// A bare keyword or identifier, e.g. `And`, `Var`.
+    Ident(String),
+    /// A double-quoted identifier, e.g. `"D"`, with the quotes stripped.
+    Str(String),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+}
+
+/// Tokenises the prefix/ADT syntax in a single pass.
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+    while let Some((start, ch)) = chars.next() {
+        match ch {
+            c if c.is_whitespace() => {}
+            '(' => tokens.push(Token::LParen),
+            ')' => tokens.push(Token::RParen),
+            '[' => tokens.push(Token::LBracket),
+            ']' => tokens.push(Token::RBracket),
+            ',' => tokens.push(Token::Comma),
+            '"' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, c)) => value.push(c),
+                        None => anyhow::bail!("unterminated string starting at byte {}", start),
+                    }
+                }
+                tokens.push(Token::Str(value));
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut value = String::from(c);
+                while let Some(&(_, c)) = chars.peek() {
+                    if c.is_alphanumeric() || c == '_' {
+                        value.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(Token::Ident(value));
+            }
+            other => anyhow::bail!("unexpected character {:?} at byte {}", other, start),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Tokenises like [`tokenize`] but never fails: it stops at the first character
+/// it cannot lex and returns the tokens gathered so far, the byte offset at which
+/// each begins, and that offending character's offset (if any). Used only by
+/// [`Parser::descent_failure_offset`] to point an error at the exact byte.
+///
+/// `spans` is one longer than `tokens`: the trailing entry is the end of input,
+/// so a cursor that runs off the end still maps to a valid offset.
+#[cfg(feature = "bnf")]
+fn tokenize_tolerant(input: &str) -> (Vec<Token>, Vec<usize>, Option<usize>) {
+    let mut tokens = Vec::new();
+    let mut spans = Vec::new();
+    let mut chars = input.char_indices().peekable();
+    let mut lex_error = None;
+    while let Some((start, ch)) = chars.next() {
+        let token = match ch {
+            c if c.is_whitespace() => continue,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '[' => Token::LBracket,
+            ']' => Token::RBracket,
+            ',' => Token::Comma,
+            '"' => {
+                let mut value = String::new();
+                let mut terminated = false;
+                for (_, c) in chars.by_ref() {
+                    if c == '"' {
+                        terminated = true;
+                        break;
+                    }
+                    value.push(c);
+                }
+                if !terminated {
+                    lex_error = Some(start);
+                    break;
+                }
+                Token::Str(value)
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut value = String::from(c);
+                while let Some(&(_, c)) = chars.peek() {
+                    if c.is_alphanumeric() || c == '_' {
+                        value.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                Token::Ident(value)
+            }
+            _ => {
+                lex_error = Some(start);
+                break;
+            }
+        };
+        tokens.push(token);
+        spans.push(start);
+    }
+    spans.push(input.len());
+    (tokens, spans, lex_error)
+}
+
+/// A forward cursor over a token slice for the recursive-descent parser.
+struct TokenCursor<'t> {
+    tokens: &'t [Token],
+    pos: usize,
+    macros: &'t MacroRegistry,
+}
+
+impl<'t> TokenCursor<'t> {
+    fn new(tokens: &'t [Token], macros: &'t MacroRegistry) -> Self {
+        Self {
+            tokens,
+            pos: 0,
+            macros,
+        }
+    }
+
+    fn peek(&self) -> Option<&'t Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<&'t Token> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .context("unexpected end of input")?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: &Token) -> Result<()> {
+        let token = self.next()?;
+        if token == expected {
+            Ok(())
+        } else {
+            anyhow::bail!("expected {:?}, found {:?}", expected, token)
+        }
+    }
+
+    /// Consumes a double-quoted identifier.
+    fn string(&mut self) -> Result<String> {
+        match self.next()? {
+            Token::Str(value) => Ok(value.clone()),
+            other => anyhow::bail!("expected a quoted identifier, found {:?}", other),
+        }
+    }
+
+    /// Parses a single formula node.
+    fn formula(&mut self) -> Result<Formula> {
+        match self.next()? {
+            Token::Ident(keyword) => match keyword.as_str() {
+                "T" => Ok(Formula::T),
+                "F" => Ok(Formula::F),
+                "Not" => Ok(Formula::Not(Box::new(self.paren_formula()?))),
+                "And" => {
+                    let (a, b) = self.two_paren_formulas()?;
+                    Ok(Formula::And(Box::new(a), Box::new(b)))
+                }
+                "Or" => {
+                    let (a, b) = self.two_paren_formulas()?;
+                    Ok(Formula::Or(Box::new(a), Box::new(b)))
+                }
+                "Implies" => {
+                    let (a, b) = self.two_paren_formulas()?;
+                    Ok(Formula::Implies(Box::new(a), Box::new(b)))
+                }
+                "Iff" => {
+                    let (a, b) = self.two_paren_formulas()?;
+                    Ok(Formula::Iff(Box::new(a), Box::new(b)))
+                }
+                "Exists" => {
+                    let var = self.string()?;
+                    Ok(Formula::Exists(var, Box::new(self.paren_formula()?)))
+                }
+                "Forall" => {
+                    let var = self.string()?;
+                    Ok(Formula::Forall(var, Box::new(self.paren_formula()?)))
+                }
+                "Rel" => {
+                    let name = self.string()?;
+                    let terms = self.term_list()?;
+                    Ok(Formula::Rel(name, terms))
+                }
+                other => match self.macros.get(other) {
+                    Some(rule) => self.expand_macro(other, rule),
+                    None => anyhow::bail!("unknown formula keyword {:?}", other),
+                },
+            },
+            Token::LParen => {
+                let inner = self.formula()?;
+                self.expect(&Token::RParen)?;
+                Ok(inner)
+            }
+            other => anyhow::bail!("expected a formula, found {:?}", other),
+        }
+    }
+
+    /// Parses a parenthesised formula: `( <formula> )`.
+    fn paren_formula(&mut self) -> Result<Formula> {
+        self.expect(&Token::LParen)?;
+        let inner = self.formula()?;
+        self.expect(&Token::RParen)?;
+        Ok(inner)
+    }
+
+    fn two_paren_formulas(&mut self) -> Result<(Formula, Formula)> {
+        Ok((self.paren_formula()?, self.paren_formula()?))
+    }
+
+    /// Parses a bracketed, comma-separated term list: `[ <term>, ... ]`.
+    fn term_list(&mut self) -> Result<Vec<FoTerm>> {
+        self.expect(&Token::LBracket)?;
+        let mut terms = Vec::new();
+        if self.peek() == Some(&Token::RBracket) {
+            self.next()?;
+            return Ok(terms);
+        }
+        loop {
+            terms.push(self.term()?);
+            match self.next()? {
+                Token::Comma => continue,
+                Token::RBracket => break,
+                other => anyhow::bail!("expected ',' or ']', found {:?}", other),
+            }
+        }
+        Ok(terms)
+    }
+
+    /// Parses a single term: `Var "x"`.
+    fn term(&mut self) -> Result<FoTerm> {
+        match self.next()? {
+            Token::Ident(keyword) if keyword == "Var" => Ok(FoTerm::Var(self.string()?)),
+            other => anyhow::bail!("expected a term, found {:?}", other),
+        }
+    }
+
+    /// Expands a call to the abbreviation `head` by parsing one parenthesised
+    /// argument per placeholder and instantiating the macro's template. Errors
+    /// name the call site so a malformed invocation points back at `head`.
+    fn expand_macro(&mut self, head: &str, rule: &Macro) -> Result<Formula> {
+        let mut bindings = HashMap::with_capacity(rule.params.len());
+        for param in &rule.params {
+            let argument = self
+                .paren_formula()
+                .with_context(|| format!("in argument {:?} of macro {:?}", param, head))?;
+            bindings.insert(param.as_str(), argument);
+        }
+        Ok(rule.template.instantiate(&bindings))
+    }
+}
+
+/// A registry of user-declared desugaring abbreviations, expanded inline while
+/// parsing the prefix/ADT syntax.
+///
+/// Borrowing the transform-on-parse idea from the dpr PEG crate, each entry is a
+/// small rewrite template keyed by its head symbol; a call substitutes the parsed
+/// argument subformulas for the template's placeholders.
+#[derive(Default)]
+struct MacroRegistry {
+    rules: HashMap<String, Macro>,
+}
+
+impl MacroRegistry {
+    fn get(&self, head: &str) -> Option<&Macro> {
+        self.rules.get(head)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Parses and stores a `Head p1 p2 := <template>` definition.
+    fn register(&mut self, definition: &str) -> Result<()> {
+        let (signature, body) = definition
+            .split_once(":=")
+            .context("macro definition must contain ':='")?;
+
+        let mut names = signature.split_whitespace();
+        let head = names
+            .next()
+            .context("macro definition is missing a head symbol")?
+            .to_string();
+        let params: Vec<String> = names.map(str::to_string).collect();
+
+        let tokens = tokenize(body)?;
+        let mut cursor = TemplateCursor::new(&tokens, &params);
+        let template = cursor
+            .template()
+            .with_context(|| format!("in body of macro {:?}", head))?;
+        if let Some(token) = cursor.peek() {
+            anyhow::bail!("unexpected trailing input in macro {:?}: {:?}", head, token);
+        }
+
+        self.rules.insert(head, Macro { params, template });
+        Ok(())
+    }
+}
+
+/// A parsed abbreviation: its placeholder names and the template they fill.
+struct Macro {
+    params: Vec<String>,
+    template: Template,
+}
+
+/// A macro-expansion template — the prefix/ADT form of [`Formula`] extended with
+/// `Placeholder` leaves that are replaced by parsed argument subformulas at the
+/// call site.
+enum Template {
+    T,
+    F,
+    Placeholder(String),
+    Not(Box<Template>),
+    And(Box<Template>, Box<Template>),
+    Or(Box<Template>, Box<Template>),
+    Implies(Box<Template>, Box<Template>),
+    Iff(Box<Template>, Box<Template>),
+    Exists(String, Box<Template>),
+    Forall(String, Box<Template>),
+    Rel(String, Vec<FoTerm>),
+}
+
+impl Template {
+    /// Builds a concrete [`Formula`], substituting each placeholder with its
+    /// bound argument. Binding completeness is guaranteed by construction:
+    /// [`TemplateCursor`] only accepts placeholders drawn from the parameter list.
+    fn instantiate(&self, bindings: &HashMap<&str, Formula>) -> Formula {
+        match self {
+            Template::T => Formula::T,
+            Template::F => Formula::F,
+            Template::Placeholder(name) => bindings[name.as_str()].clone(),
+            Template::Not(a) => Formula::Not(Box::new(a.instantiate(bindings))),
+            Template::And(a, b) => Formula::And(
+                Box::new(a.instantiate(bindings)),
+                Box::new(b.instantiate(bindings)),
+            ),
+            Template::Or(a, b) => Formula::Or(
+                Box::new(a.instantiate(bindings)),
+                Box::new(b.instantiate(bindings)),
+            ),
+            Template::Implies(a, b) => Formula::Implies(
+                Box::new(a.instantiate(bindings)),
+                Box::new(b.instantiate(bindings)),
+            ),
+            Template::Iff(a, b) => Formula::Iff(
+                Box::new(a.instantiate(bindings)),
+                Box::new(b.instantiate(bindings)),
+            ),
+            Template::Exists(var, a) => {
+                Formula::Exists(var.clone(), Box::new(a.instantiate(bindings)))
+            }
+            Template::Forall(var, a) => {
+                Formula::Forall(var.clone(), Box::new(a.instantiate(bindings)))
+            }
+            Template::Rel(name, terms) => Formula::Rel(name.clone(), terms.clone()),
+        }
+    }
+}
+
+/// A recursive-descent cursor that parses a macro body into a [`Template`],
+/// recognising bare identifiers drawn from `params` as placeholders. It mirrors
+/// [`TokenCursor::formula`] but never expands nested macros.
+struct TemplateCursor<'t> {
+    tokens: &'t [Token],
+    pos: usize,
+    params: &'t [String],
+}
+
+impl<'t> TemplateCursor<'t> {
+    fn new(tokens: &'t [Token], params: &'t [String]) -> Self {
+        Self {
+            tokens,
+            pos: 0,
+            params,
+        }
+    }
+
+    fn peek(&self) -> Option<&'t Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<&'t Token> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .context("unexpected end of input")?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: &Token) -> Result<()> {
+        let token = self.next()?;
+        if token == expected {
+            Ok(())
+        } else {
+            anyhow::bail!("expected {:?}, found {:?}", expected, token)
+        }
+    }
+
+    fn string(&mut self) -> Result<String> {
+        match self.next()? {
+            Token::Str(value) => Ok(value.clone()),
+            other => anyhow::bail!("expected a quoted identifier, found {:?}", other),
+        }
+    }
+
+    fn template(&mut self) -> Result<Template> {
+        match self.next()? {
+            Token::Ident(keyword) => match keyword.as_str() {
+                "T" => Ok(Template::T),
+                "F" => Ok(Template::F),
+                "Not" => Ok(Template::Not(Box::new(self.paren_template()?))),
+                "And" => {
+                    let (a, b) = self.two_paren_templates()?;
+                    Ok(Template::And(Box::new(a), Box::new(b)))
+                }
+                "Or" => {
+                    let (a, b) = self.two_paren_templates()?;
+                    Ok(Template::Or(Box::new(a), Box::new(b)))
+                }
+                "Implies" => {
+                    let (a, b) = self.two_paren_templates()?;
+                    Ok(Template::Implies(Box::new(a), Box::new(b)))
+                }
+                "Iff" => {
+                    let (a, b) = self.two_paren_templates()?;
+                    Ok(Template::Iff(Box::new(a), Box::new(b)))
+                }
+                "Exists" => {
+                    let var = self.string()?;
+                    Ok(Template::Exists(var, Box::new(self.paren_template()?)))
+                }
+                "Forall" => {
+                    let var = self.string()?;
+                    Ok(Template::Forall(var, Box::new(self.paren_template()?)))
+                }
+                "Rel" => {
+                    let name = self.string()?;
+                    let terms = self.term_list()?;
+                    Ok(Template::Rel(name, terms))
+                }
+                name if self.params.iter().any(|p| p == name) => {
+                    Ok(Template::Placeholder(name.to_string()))
+                }
+                other => anyhow::bail!("unknown template keyword {:?}", other),
+            },
+            Token::LParen => {
+                let inner = self.template()?;
+                self.expect(&Token::RParen)?;
+                Ok(inner)
+            }
+            other => anyhow::bail!("expected a template formula, found {:?}", other),
+        }
+    }
+
+    fn paren_template(&mut self) -> Result<Template> {
+        self.expect(&Token::LParen)?;
+        let inner = self.template()?;
+        self.expect(&Token::RParen)?;
+        Ok(inner)
+    }
+
+    fn two_paren_templates(&mut self) -> Result<(Template, Template)> {
+        Ok((self.paren_template()?, self.paren_template()?))
+    }
+
+    fn term_list(&mut self) -> Result<Vec<FoTerm>> {
+        self.expect(&Token::LBracket)?;
+        let mut terms = Vec::new();
+        if self.peek() == Some(&Token::RBracket) {
+            self.next()?;
+            return Ok(terms);
+        }
+        loop {
+            match self.next()? {
+                Token::Ident(keyword) if keyword == "Var" => {
+                    terms.push(FoTerm::Var(self.string()?))
+                }
+                other => anyhow::bail!("expected a term, found {:?}", other),
+            }
+            match self.next()? {
+                Token::Comma => continue,
+                Token::RBracket => break,
+                other => anyhow::bail!("expected ',' or ']', found {:?}", other),
+            }
+        }
+        Ok(terms)
+    }
+}
+
+/// A lexical token of the conventional infix/Unicode syntax accepted by
+/// [`Parser::parse_infix`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum InfixToken {
+    Not,
+    And,
+    Or,
+    Implies,
+    Iff,
+    Forall,
+    Exists,
+    Dot,
+    LParen,
+    RParen,
+    Comma,
+    /// A true/false constant (`⊤`/`⊥`).
+    Const(bool),
+    /// A relation name or variable identifier.
+    Ident(String),
+}
+
+/// Tokenises the infix syntax, accepting both ASCII and Unicode spellings of the
+/// connectives (`&`/`∧`, `|`/`∨`, `->`/`→`, `<->`/`↔`, `~`/`¬`).
+fn tokenize_infix(input: &str) -> Result<Vec<InfixToken>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            c if c.is_whitespace() => {}
+            '(' => tokens.push(InfixToken::LParen),
+            ')' => tokens.push(InfixToken::RParen),
+            ',' => tokens.push(InfixToken::Comma),
+            '.' => tokens.push(InfixToken::Dot),
+            '&' | '∧' => tokens.push(InfixToken::And),
+            '|' | '∨' => tokens.push(InfixToken::Or),
+            '~' | '¬' => tokens.push(InfixToken::Not),
+            '→' => tokens.push(InfixToken::Implies),
+            '↔' => tokens.push(InfixToken::Iff),
+            '∀' => tokens.push(InfixToken::Forall),
+            '∃' => tokens.push(InfixToken::Exists),
+            '⊤' => tokens.push(InfixToken::Const(true)),
+            '⊥' => tokens.push(InfixToken::Const(false)),
+            '-' => {
+                if chars.next_if_eq(&'>').is_some() {
+                    tokens.push(InfixToken::Implies);
+                } else {
+                    anyhow::bail!("expected '->' after '-'");
+                }
+            }
+            '<' => {
+                if chars.next_if_eq(&'-').is_some() && chars.next_if_eq(&'>').is_some() {
+                    tokens.push(InfixToken::Iff);
+                } else {
+                    anyhow::bail!("expected '<->'");
+                }
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut value = String::from(c);
+                while let Some(&c) = chars.peek() {
+                    if c.is_alphanumeric() || c == '_' {
+                        value.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                match value.as_str() {
+                    "forall" => tokens.push(InfixToken::Forall),
+                    "exists" => tokens.push(InfixToken::Exists),
+                    _ => tokens.push(InfixToken::Ident(value)),
+                }
+            }
+            other => anyhow::bail!("unexpected character {:?}", other),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Precedence-climbing parser over the infix token stream.
+///
+/// Binding tightness, loosest to tightest: `↔`, `→` (right-associative), `∨`,
+/// `∧`, then the prefix `¬` and quantifiers. A quantifier's body extends as far
+/// right as possible (to the enclosing `)` or the end of input), whatever
+/// operator precedes the quantifier, matching textbook convention.
+struct InfixCursor<'t> {
+    tokens: &'t [InfixToken],
+    pos: usize,
+}
+
+impl<'t> InfixCursor<'t> {
+    fn new(tokens: &'t [InfixToken]) -> Self {
+        Self { tokens, pos: 0 }
+    }
+
+    fn peek(&self) -> Option<&'t InfixToken> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<&'t InfixToken> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .context("unexpected end of input")?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn eat(&mut self, expected: &InfixToken) -> Result<()> {
+        let token = self.next()?;
+        if token == expected {
+            Ok(())
+        } else {
+            anyhow::bail!("expected {:?}, found {:?}", expected, token)
+        }
+    }
+
+    /// `<iff> ::= <implies> ('↔' <implies>)*` (left-associative).
+    fn iff(&mut self) -> Result<Formula> {
+        let mut lhs = self.implies()?;
+        while self.peek() == Some(&InfixToken::Iff) {
+            self.next()?;
+            let rhs = self.implies()?;
+            lhs = Formula::Iff(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    /// `<implies> ::= <or> ('→' <implies>)?` (right-associative).
+    fn implies(&mut self) -> Result<Formula> {
+        let lhs = self.or()?;
+        if self.peek() == Some(&InfixToken::Implies) {
+            self.next()?;
+            let rhs = self.implies()?;
+            Ok(Formula::Implies(Box::new(lhs), Box::new(rhs)))
+        } else {
+            Ok(lhs)
+        }
+    }
+
+    /// `<or> ::= <and> ('∨' <and>)*`.
+    fn or(&mut self) -> Result<Formula> {
+        let mut lhs = self.and()?;
+        while self.peek() == Some(&InfixToken::Or) {
+            self.next()?;
+            let rhs = self.and()?;
+            lhs = Formula::Or(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    /// `<and> ::= <unary> ('∧' <unary>)*`.
+    fn and(&mut self) -> Result<Formula> {
+        let mut lhs = self.unary()?;
+        while self.peek() == Some(&InfixToken::And) {
+            self.next()?;
+            let rhs = self.unary()?;
+            lhs = Formula::And(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    /// Prefix `¬` and the quantifiers, or a primary. A quantifier binds its body
+    /// down at the loosest level so `∀y. D(y) → P` reads as `∀y. (D(y) → P)`.
+    fn unary(&mut self) -> Result<Formula> {
+        match self.peek() {
+            Some(InfixToken::Not) => {
+                self.next()?;
+                Ok(Formula::Not(Box::new(self.unary()?)))
+            }
+            Some(InfixToken::Forall) => {
+                self.next()?;
+                let var = self.ident()?;
+                self.eat(&InfixToken::Dot)?;
+                Ok(Formula::Forall(var, Box::new(self.iff()?)))
+            }
+            Some(InfixToken::Exists) => {
+                self.next()?;
+                let var = self.ident()?;
+                self.eat(&InfixToken::Dot)?;
+                Ok(Formula::Exists(var, Box::new(self.iff()?)))
+            }
+            _ => self.primary(),
+        }
+    }
+
+    /// A parenthesised formula, a constant, or an applied relation `R(t, ...)`.
+    fn primary(&mut self) -> Result<Formula> {
+        match self.next()? {
+            InfixToken::LParen => {
+                let inner = self.iff()?;
+                self.eat(&InfixToken::RParen)?;
+                Ok(inner)
+            }
+            InfixToken::Const(true) => Ok(Formula::T),
+            InfixToken::Const(false) => Ok(Formula::F),
+            InfixToken::Ident(name) => {
+                let name = name.clone();
+                let mut terms = Vec::new();
+                if self.peek() == Some(&InfixToken::LParen) {
+                    self.next()?;
+                    if self.peek() != Some(&InfixToken::RParen) {
+                        loop {
+                            terms.push(FoTerm::Var(self.ident()?));
+                            match self.next()? {
+                                InfixToken::Comma => continue,
+                                InfixToken::RParen => break,
+                                other => anyhow::bail!("expected ',' or ')', found {:?}", other),
+                            }
+                        }
+                    } else {
+                        self.next()?;
+                    }
+                }
+                Ok(Formula::Rel(name, terms))
+            }
+            other => anyhow::bail!("expected a formula, found {:?}", other),
+        }
+    }
+
+    fn ident(&mut self) -> Result<String> {
+        match self.next()? {
+            InfixToken::Ident(name) => Ok(name.clone()),
+            other => anyhow::bail!("expected an identifier, found {:?}", other),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "bnf")]
     use anyhow::Context;
+    #[cfg(feature = "bnf")]
     use bnf::Grammar;
 
+    #[cfg(feature = "bnf")]
     use crate::tests::for_each_external_test;
 
     use super::Parser;
@@ -48,6 +1066,7 @@ mod tests {
         let _parser = Parser::new().unwrap();
     }
 
+    #[cfg(feature = "bnf")]
     #[test]
     fn experiments() {
         let grammar: Grammar = "
@@ -76,6 +1095,7 @@ mod tests {
             .unwrap();
     }
 
+    #[cfg(feature = "bnf")]
     #[test]
     fn good_inputs_are_parsed() {
         let parser = Parser::new().unwrap();
@@ -95,7 +1115,13 @@ mod tests {
             ];
 
             for formula in formulas {
-                parser.with_parse_tree(formula, |_| ()).unwrap();
+                // The hand-written recursive-descent backend must agree with the
+                // bnf Earley engine on every well-formed input. This test only
+                // builds with the `bnf` feature, where `parse` drives the Earley
+                // engine, so the comparison is genuinely bnf-vs-recursive-descent.
+                let via_bnf = parser.parse(formula).unwrap();
+                let via_rd = parser.parse_recursive(formula).unwrap();
+                assert_eq!(via_bnf, via_rd, "backends disagree on {:?}", formula);
             }
         }
 
@@ -103,4 +1129,159 @@ mod tests {
             parser.with_parse_tree(input, |_| ()).unwrap();
         });
     }
+
+    /// Structural round-trip property for a single parsed formula: pretty-print
+    /// it, re-parse the printout, and require the two `Formula` values to be equal.
+    /// A failure is either a parser bug or a pretty-printer divergence.
+    #[cfg(feature = "bnf")]
+    fn round_trips(parser: &Parser, formula: &super::Formula) -> bool {
+        let printed = formula.to_string();
+        matches!(parser.parse(&printed), Ok(reparsed) if &reparsed == formula)
+    }
+
+    /// Structural shrinks of `formula`: the atoms first, then each immediate
+    /// subformula, so minimisation converges on a minimal counterexample.
+    #[cfg(feature = "bnf")]
+    fn shrinks(formula: &super::Formula) -> Vec<super::Formula> {
+        use super::Formula::*;
+        let mut candidates = vec![T, F];
+        match formula {
+            Not(a) => candidates.push((**a).clone()),
+            And(a, b) | Or(a, b) | Implies(a, b) | Iff(a, b) => {
+                candidates.push((**a).clone());
+                candidates.push((**b).clone());
+            }
+            Exists(_, a) | Forall(_, a) => candidates.push((**a).clone()),
+            _ => {}
+        }
+        candidates
+    }
+
+    /// Greedily reduces a failing `formula` to a minimal one that still fails the
+    /// round-trip property, mirroring a `proptest`/`arbitrary` shrinker.
+    #[cfg(feature = "bnf")]
+    fn minimize(parser: &Parser, mut formula: super::Formula) -> super::Formula {
+        'outer: loop {
+            for candidate in shrinks(&formula) {
+                if candidate != formula && !round_trips(parser, &candidate) {
+                    formula = candidate;
+                    continue 'outer;
+                }
+            }
+            return formula;
+        }
+    }
+
+    #[cfg(feature = "bnf")]
+    #[test]
+    fn generated_formulas_round_trip() {
+        let parser = Parser::new().unwrap();
+        let grammar: Grammar = super::BNF_GRAMMAR.parse().unwrap();
+
+        // Drive thousands of cases for free off the grammar the parser already
+        // holds, instead of the handful of hardcoded strings above.
+        let mut checked = 0;
+        for _ in 0..2_000 {
+            // `generate()` can bail on an over-deep expansion; skip those.
+            let source = match grammar.generate() {
+                Ok(source) => source,
+                Err(_) => continue,
+            };
+            let formula = match parser.parse(&source) {
+                Ok(formula) => formula,
+                Err(_) => continue,
+            };
+            if !round_trips(&parser, &formula) {
+                let minimal = minimize(&parser, formula);
+                panic!("round-trip failed; minimal counterexample: {}", minimal);
+            }
+            checked += 1;
+        }
+
+        assert!(checked > 0, "generator produced no parseable formulas");
+    }
+
+    #[cfg(feature = "bnf")]
+    #[test]
+    fn bad_input_reports_structured_error() {
+        let parser = Parser::new().unwrap();
+
+        // A well-formed prefix followed by garbage: the error should land past
+        // the accepted prefix rather than at offset zero.
+        let error = parser
+            .parse_recovering("And (T) (%%%)")
+            .1
+            .into_iter()
+            .next()
+            .expect("malformed input should produce a ParseError");
+
+        assert!(error.offset > 0, "expected a non-trivial failure offset");
+        assert_eq!(error.line, 1);
+    }
+
+    #[test]
+    fn infix_frontend_matches_prefix() {
+        let parser = Parser::new().unwrap();
+
+        // Each pair spells the same formula in textbook infix notation and in the
+        // verbose prefix/ADT form; both frontends must land on the same `Formula`.
+        let pairs = [
+            ("D(x)", r#"Rel "D" [Var "x"]"#),
+            ("~D(x)", r#"Not (Rel "D" [Var "x"])"#),
+            (
+                "D(x) & E(y) | F(z)",
+                r#"Or (And (Rel "D" [Var "x"]) (Rel "E" [Var "y"])) (Rel "F" [Var "z"])"#,
+            ),
+            (
+                "exists x. (D(x) -> forall y. D(y))",
+                r#"Exists "x" (Implies (Rel "D" [Var "x"]) (Forall "y" (Rel "D" [Var "y"])))"#,
+            ),
+            (
+                "∀y. D(y) ↔ ⊤",
+                r#"Forall "y" (Iff (Rel "D" [Var "y"]) (T))"#,
+            ),
+        ];
+
+        for (infix, prefix) in pairs {
+            let via_infix = parser.parse_infix(infix).unwrap();
+            let via_prefix = parser.parse(prefix).unwrap();
+            assert_eq!(via_infix, via_prefix, "frontends disagree on {:?}", infix);
+        }
+
+        // `→` is right-associative: `a -> b -> c` parses as `a -> (b -> c)`.
+        let right_assoc = parser.parse_infix("A -> B -> C").unwrap();
+        let grouped = parser.parse_infix("A -> (B -> C)").unwrap();
+        assert_eq!(right_assoc, grouped);
+    }
+
+    #[test]
+    fn registered_macro_desugars_to_core_formula() {
+        let mut parser = Parser::new().unwrap();
+        parser
+            .define_macro("Xor a b := And (Or (a) (b)) (Not (And (a) (b)))")
+            .unwrap();
+
+        // Exercise the public `parse` entry point so the behaviour is verified on
+        // the path callers use, not only the recursive-descent backend — with the
+        // `bnf` feature on, `parse` must still expand the abbreviation.
+        let expanded = parser
+            .parse(r#"Xor (Rel "P" [Var "x"]) (Rel "Q" [Var "y"])"#)
+            .unwrap();
+        let core = parser
+            .parse(
+                r#"And (Or (Rel "P" [Var "x"]) (Rel "Q" [Var "y"])) (Not (And (Rel "P" [Var "x"]) (Rel "Q" [Var "y"])))"#,
+            )
+            .unwrap();
+        assert_eq!(expanded, core);
+
+        // A malformed invocation names the call site so the error is actionable.
+        let error = parser
+            .parse(r#"Xor (T)"#)
+            .expect_err("too few arguments should fail");
+        assert!(
+            format!("{:#}", error).contains("Xor"),
+            "error should point at the macro call site: {:#}",
+            error
+        );
+    }
 }